@@ -0,0 +1,198 @@
+//! Playback engine running on its own thread, talking to the UI as a peer
+//! over two channels instead of sharing a `Sink` behind a mutex. The UI
+//! sends `Command`s and polls for `Status` events; the controller owns
+//! `OutputStream`/`Sink` and is the only thing that touches rodio directly.
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use rodio::{OutputStream, Sink, Source};
+
+use crate::decode::{self, SymphoniaSource};
+
+/// How often the controller reports `Status::Position` and checks whether
+/// the current track has run out while idling on its command channel.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub enum Command {
+    Load(PathBuf),
+    /// Decodes `PathBuf` and appends it to the sink's queue without
+    /// interrupting what's currently playing, so it starts the instant the
+    /// current track ends (gapless playback).
+    QueueNext(PathBuf),
+    Play,
+    Pause,
+    Stop,
+    Seek(f32),
+    SetVolume(f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    NowPlaying,
+    Paused,
+    Stopped,
+    Position(f32),
+    /// The sink advanced on its own from the current track into the one
+    /// queued with `QueueNext`, without an explicit `Load`.
+    TrackChanged,
+    TrackFinished,
+}
+
+/// A track currently held by the sink, along with the duration needed to
+/// tell when the sink's cumulative position has crossed into the next one.
+struct QueuedTrack {
+    path: PathBuf,
+    duration_secs: f32,
+}
+
+pub struct AudioController {
+    commands: Sender<Command>,
+    status: Receiver<Status>,
+}
+
+impl AudioController {
+    /// Spawns the playback thread and returns the handle the UI talks to.
+    pub fn spawn() -> Self {
+        let (command_tx, command_rx) = mpsc::channel();
+        let (status_tx, status_rx) = mpsc::channel();
+
+        thread::spawn(move || run(command_rx, status_tx));
+
+        Self {
+            commands: command_tx,
+            status: status_rx,
+        }
+    }
+
+    pub fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+
+    /// Non-blocking; call once per frame and drain until `None`.
+    pub fn try_recv_status(&self) -> Option<Status> {
+        self.status.try_recv().ok()
+    }
+}
+
+fn run(commands: Receiver<Command>, status: Sender<Status>) {
+    let (_stream, stream_handle) = match OutputStream::try_default() {
+        Ok(pair) => pair,
+        Err(_) => return,
+    };
+    let sink = match Sink::try_new(&stream_handle) {
+        Ok(sink) => sink,
+        Err(_) => return,
+    };
+
+    let mut current: Option<QueuedTrack> = None;
+    let mut queued: Option<QueuedTrack> = None;
+    // O Sink reporta a posição cumulativa desde o último `append` após um
+    // `stop`; `seek_offset` é somado a isso para refletir a posição real
+    // dentro da faixa atual, tanto após um seek quanto após o sink avançar
+    // sozinho para a faixa enfileirada via `QueueNext`.
+    let mut seek_offset = 0.0_f32;
+
+    loop {
+        match commands.recv_timeout(POLL_INTERVAL) {
+            Ok(Command::Load(path)) => {
+                sink.stop();
+                seek_offset = 0.0;
+                queued = None;
+                match SymphoniaSource::new(&path) {
+                    Some(source) => {
+                        let duration_secs = decode::probe_metadata(&path)
+                            .map(|metadata| metadata.duration_secs)
+                            .unwrap_or(0.0);
+                        sink.append(source);
+                        sink.play();
+                        current = Some(QueuedTrack { path, duration_secs });
+                        let _ = status.send(Status::NowPlaying);
+                    }
+                    None => {
+                        // Arquivo não decodificável: nada para tocar, avisa a UI
+                        // para que ela não fique presa num estado "tocando" fantasma.
+                        current = None;
+                        let _ = status.send(Status::Stopped);
+                    }
+                }
+            }
+            Ok(Command::QueueNext(path)) => {
+                if let Some(source) = SymphoniaSource::new(&path) {
+                    let duration_secs = decode::probe_metadata(&path)
+                        .map(|metadata| metadata.duration_secs)
+                        .unwrap_or(0.0);
+                    sink.append(source);
+                    queued = Some(QueuedTrack { path, duration_secs });
+                }
+            }
+            Ok(Command::Play) => {
+                sink.play();
+                let _ = status.send(Status::NowPlaying);
+            }
+            Ok(Command::Pause) => {
+                sink.pause();
+                let _ = status.send(Status::Paused);
+            }
+            Ok(Command::Stop) => {
+                sink.stop();
+                current = None;
+                queued = None;
+                seek_offset = 0.0;
+                let _ = status.send(Status::Stopped);
+            }
+            Ok(Command::Seek(position)) => {
+                if let Some(track) = &current {
+                    sink.stop();
+                    if let Some(source) = SymphoniaSource::new(&track.path) {
+                        let skipped = source.skip_duration(Duration::from_secs_f32(position));
+                        sink.append(skipped);
+                        sink.play();
+                        seek_offset = position;
+                        queued = None; // o que estava enfileirado ficou para trás no sink
+                        let _ = status.send(Status::Position(position));
+                    }
+                }
+            }
+            Ok(Command::SetVolume(volume)) => sink.set_volume(volume),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if current.is_none() {
+            continue;
+        }
+
+        if sink.empty() {
+            current = None;
+            queued = None;
+            seek_offset = 0.0;
+            let _ = status.send(Status::TrackFinished);
+            continue;
+        }
+
+        if sink.is_paused() {
+            continue;
+        }
+
+        let position = seek_offset + sink.get_pos().as_secs_f32();
+        let current_duration = current.as_ref().map(|t| t.duration_secs).unwrap_or(0.0);
+
+        if let Some(next) = queued.take() {
+            if current_duration > 0.0 && position + 0.05 >= current_duration {
+                // O Sink avançou sozinho para a faixa enfileirada: a posição
+                // cumulativa continua subindo a partir daqui, então deslocamos
+                // `seek_offset` pela duração da faixa que terminou.
+                seek_offset -= current_duration;
+                current = Some(next);
+                let _ = status.send(Status::TrackChanged);
+                continue;
+            }
+            queued = Some(next);
+        }
+
+        let _ = status.send(Status::Position(position));
+    }
+}
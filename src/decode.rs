@@ -0,0 +1,199 @@
+//! Thin wrapper around `symphonia` that replaces the old mp3-only pipeline
+//! (`id3::Tag` + `mp3_duration` + `rodio::Decoder`). Symphonia probes the
+//! container, exposes standard metadata tags and gives us exact duration
+//! from the codec parameters instead of scanning the whole file.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+
+use rodio::Source;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey};
+use symphonia::core::probe::Hint;
+
+/// Extensões que o probe do Symphonia reconhece nesta build (features
+/// `mp3`, `isomp4`, `aac`, `flac`, `vorbis`, `wav`).
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["mp3", "flac", "aac", "m4a", "ogg", "wav"];
+
+pub struct TrackMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub duration_secs: f32,
+}
+
+fn probe(path: &Path) -> Option<Box<dyn FormatReader>> {
+    let file = File::open(path).ok()?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()
+        .map(|probed| probed.format)
+}
+
+/// Tenta abrir e identificar o container/track sem decodificar nada, para
+/// filtrar arquivos corrompidos ou com extensão enganosa ao listar um
+/// diretório.
+pub fn can_decode(path: &Path) -> bool {
+    probe(path)
+        .map(|format| {
+            format
+                .tracks()
+                .iter()
+                .any(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        })
+        .unwrap_or(false)
+}
+
+/// Lê título/artista e calcula a duração exata a partir de `n_frames` e
+/// `sample_rate` dos parâmetros do codec, sem decodificar a música inteira.
+pub fn probe_metadata(path: &Path) -> Option<TrackMetadata> {
+    let mut format = probe(path)?;
+
+    let mut title = None;
+    let mut artist = None;
+    if let Some(revision) = format.metadata().current() {
+        for tag in revision.tags() {
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => title = Some(tag.value.to_string()),
+                Some(StandardTagKey::Artist) => artist = Some(tag.value.to_string()),
+                _ => {}
+            }
+        }
+    }
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)?;
+    let duration_secs = track
+        .codec_params
+        .n_frames
+        .zip(track.codec_params.sample_rate)
+        .map(|(frames, rate)| frames as f32 / rate as f32)
+        .unwrap_or(0.0);
+
+    Some(TrackMetadata {
+        title,
+        artist,
+        duration_secs,
+    })
+}
+
+/// `rodio::Source` que decodifica um arquivo sob demanda via Symphonia,
+/// um pacote por vez, em vez de carregar tudo em memória.
+pub struct SymphoniaSource {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    channels: u16,
+    sample_rate: u32,
+    buffer: VecDeque<f32>,
+}
+
+impl SymphoniaSource {
+    pub fn new(path: &Path) -> Option<Self> {
+        let format = probe(path)?;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)?;
+        let track_id = track.id;
+        let channels = track
+            .codec_params
+            .channels
+            .map(|c| c.count() as u16)
+            .unwrap_or(2);
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(44_100);
+
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .ok()?;
+
+        Some(Self {
+            format,
+            decoder,
+            track_id,
+            channels,
+            sample_rate,
+            buffer: VecDeque::new(),
+        })
+    }
+
+    /// Decodifica o próximo pacote do track de interesse para dentro do buffer.
+    /// Retorna `false` quando não há mais áudio para ler.
+    fn refill(&mut self) -> bool {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(_) => return false,
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            match self.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    let spec = *decoded.spec();
+                    self.channels = spec.channels.count() as u16;
+                    self.sample_rate = spec.rate;
+
+                    let mut sample_buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                    sample_buf.copy_interleaved_ref(decoded);
+                    self.buffer.extend(sample_buf.samples().iter().copied());
+                    if self.buffer.is_empty() {
+                        continue; // pacote sem amostras (ex.: o primeiro do Vorbis), lê o próximo
+                    }
+                    return true;
+                }
+                Err(SymphoniaError::DecodeError(_)) => continue, // pacote corrompido, tenta o próximo
+                Err(_) => return false,
+            }
+        }
+    }
+}
+
+impl Iterator for SymphoniaSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.buffer.is_empty() && !self.refill() {
+            return None;
+        }
+        self.buffer.pop_front()
+    }
+}
+
+impl Source for SymphoniaSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels.max(1)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate.max(1)
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
@@ -1,34 +1,77 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 #![allow(rustdoc::missing_crate_level_docs)] // it's an example
 
+mod audio_controller;
+mod decode;
+mod playlist;
+
+use audio_controller::{AudioController, Command, Status};
 use dirs::home_dir;
 use eframe::egui::{self, Slider, Layout, Align, Direction};
-use id3::Tag;
-use id3::TagLike;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use playlist::Playlist;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use rfd::FileDialog;
-use rodio::Source;
-use rodio::{Decoder, OutputStream, Sink};
-use std::fs::{self, File};
-use std::io::BufReader;
+use std::fs;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc;
+
+/// Regra aplicada quando uma música termina ou quando o usuário clica "Next"/"Back".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PlaybackMode {
+    Normal,
+    RepeatOne,
+    RepeatAll,
+    Shuffle,
+}
+
+impl PlaybackMode {
+    fn label(&self) -> &'static str {
+        match self {
+            PlaybackMode::Normal => "Normal",
+            PlaybackMode::RepeatOne => "Repeat One",
+            PlaybackMode::RepeatAll => "Repeat All",
+            PlaybackMode::Shuffle => "Shuffle",
+        }
+    }
+}
+
+/// Depois de quantos segundos de reprodução "Back" reinicia a música atual
+/// em vez de voltar para a anterior (comportamento do Spotify).
+const RESTART_ON_BACK_THRESHOLD_SECS: f32 = 3.0;
+
+/// Margem de tolerância para considerar uma faixa terminada a partir da
+/// posição reportada pelo `AudioController`, evitando perder o último
+/// instante por arredondamento.
+const TRACK_END_EPSILON_SECS: f32 = 0.05;
 
 struct MediaPlayerApp {
     song_title: String,
     artist_name: String,
-    sink: Option<Arc<Mutex<Sink>>>,
-    _stream: Option<OutputStream>, // To keep the stream alive
+    controller: AudioController,
     current_time: f32,
     total_time: f32,
-    start_time: Option<std::time::Instant>,
     volume: f32,
     songs: Vec<PathBuf>,
     selected_song: Option<usize>,
     current_directory: String, // Campo para armazenar o diretório atual
     is_playing: bool,          // Estado de reprodução
-    song_finished: Arc<AtomicBool>, // Indica se a música terminou
     layout: LayoutSettings,
+    preloaded_index: Option<usize>, // Índice já enfileirado no AudioController para tocar sem gap
+    advanced_for_current: bool, // Evita avançar duas vezes para a mesma faixa que terminou
+    playback_mode: PlaybackMode,
+    shuffle_order: Vec<usize>, // Pilha com os próximos índices embaralhados (consumida do final)
+    history: Vec<usize>,      // Índices já tocados, usado por previous_song() no modo Shuffle
+    metadata_tx: mpsc::Sender<(usize, Option<decode::TrackMetadata>)>,
+    metadata_rx: mpsc::Receiver<(usize, Option<decode::TrackMetadata>)>,
+    scan_tx: mpsc::Sender<(u64, PathBuf)>,
+    scan_rx: mpsc::Receiver<(u64, PathBuf)>,
+    scan_generation: u64, // Descarta resultados de um scan de diretório anterior, já abandonado
+    playlists: Vec<Playlist>,
+    search_query: String,
+    matcher: SkimMatcherV2,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -76,229 +119,494 @@ impl Default for MediaPlayerApp {
             .unwrap_or_else(|| PathBuf::from("."))
             .to_string_lossy()
             .to_string(); // Diretório inicial
-        let songs = Self::read_songs_from_directory(&initial_directory);
-        let (_stream, stream_handle) = OutputStream::try_default().unwrap(); // Mantém o stream durante toda a vida útil
-        Self {
+        let (metadata_tx, metadata_rx) = mpsc::channel();
+        let (scan_tx, scan_rx) = mpsc::channel();
+        let mut app = Self {
             song_title: "Song Title".to_owned(),
             artist_name: "Unknown Artist".to_owned(),
-            sink: Some(Arc::new(Mutex::new(Sink::try_new(&stream_handle).unwrap()))),
-            _stream: Some(_stream), // Mantém o stream vivo
+            controller: AudioController::spawn(),
             current_time: 0.0,
             total_time: 200.0, // Exemplo de tempo total em segundos (e.g., 3:20)
-            start_time: None,
             volume: 0.5,
-            songs,
+            songs: Vec::new(),
             selected_song: None,
             current_directory: initial_directory,
             is_playing: false,
-            song_finished: Arc::new(AtomicBool::new(false)),
             layout: LayoutSettings::default(),
-        }
+            preloaded_index: None,
+            advanced_for_current: false,
+            playback_mode: PlaybackMode::Normal,
+            shuffle_order: Vec::new(),
+            history: Vec::new(),
+            metadata_tx,
+            metadata_rx,
+            scan_tx,
+            scan_rx,
+            scan_generation: 0,
+            playlists: playlist::load(),
+            search_query: String::new(),
+            matcher: SkimMatcherV2::default(),
+        };
+        let initial_directory = app.current_directory.clone();
+        app.spawn_directory_scan(&initial_directory);
+        app
     }
 }
 
 impl MediaPlayerApp {
-    fn read_songs_from_directory(directory: &str) -> Vec<PathBuf> {
-        let mut songs = vec![];
+    /// Lista candidatos só pela extensão — rápido o bastante para rodar na
+    /// UI thread mesmo em diretórios grandes ou montados em rede. Quem chama
+    /// ainda precisa confirmar cada um com `decode::can_decode` antes de
+    /// tratá-lo como tocável (ver `spawn_directory_scan`).
+    fn list_candidate_files(directory: &str) -> Vec<PathBuf> {
+        let mut candidates = vec![];
         if let Ok(entries) = fs::read_dir(directory) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_file() && path.extension().map(|ext| ext == "mp3").unwrap_or(false) {
-                    songs.push(path);
+                let has_supported_extension = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .map(|ext| {
+                        decode::SUPPORTED_EXTENSIONS
+                            .iter()
+                            .any(|supported| supported.eq_ignore_ascii_case(ext))
+                    })
+                    .unwrap_or(false);
+                if path.is_file() && has_supported_extension {
+                    candidates.push(path);
                 }
             }
         }
-        songs
+        candidates
     }
 
-    fn previous_song(&mut self) {
-        if let Some(current_index) = self.selected_song {
-            if current_index > 0 {
-                self.selected_song = Some(current_index - 1);
-                self.load_and_play_song();
+    /// Lista os candidatos do diretório (rápido, síncrono) e manda o probe do
+    /// Symphonia — que abre e decodifica o cabeçalho de cada arquivo — para
+    /// uma thread separada, em vez de travar a UI nele como antes. `songs`
+    /// começa vazio e recebe cada arquivo confirmado conforme o probe chega,
+    /// via `poll_directory_scan`.
+    fn spawn_directory_scan(&mut self, directory: &str) {
+        self.scan_generation += 1;
+        let generation = self.scan_generation;
+        self.songs.clear();
+
+        let candidates = Self::list_candidate_files(directory);
+        let tx = self.scan_tx.clone();
+        std::thread::spawn(move || {
+            for path in candidates {
+                // A extensão só descartou os casos óbvios; o probe confirma que o
+                // arquivo realmente decodifica antes de entrar na lista, pulando
+                // arquivos corrompidos ou mal-rotulados.
+                if decode::can_decode(&path) {
+                    let _ = tx.send((generation, path));
+                }
             }
-        }
+        });
     }
 
-    fn next_song(&mut self) {
-        if let Some(current_index) = self.selected_song {
-            if current_index + 1 < self.songs.len() {
-                self.selected_song = Some(current_index + 1);
-                self.load_and_play_song();
-            } else {
-                self.stop(); // Se for a última música, pare a reprodução
+    /// Consome arquivos confirmados pelo scan em andamento, descartando os
+    /// que vieram de um diretório já trocado (`generation` desatualizada).
+    fn poll_directory_scan(&mut self) {
+        while let Ok((generation, path)) = self.scan_rx.try_recv() {
+            if generation != self.scan_generation {
+                continue;
             }
+            self.songs.push(path);
+        }
+    }
+
+    fn previous_song(&mut self) {
+        let Some(current_index) = self.selected_song else {
+            return;
+        };
+
+        // Regra do Spotify: depois de alguns segundos, "Back" reinicia a faixa atual.
+        if self.current_time > RESTART_ON_BACK_THRESHOLD_SECS {
+            self.force_load(current_index);
+            return;
+        }
+
+        let target_index = match self.playback_mode {
+            PlaybackMode::Shuffle => self.history.pop(),
+            _ => (current_index > 0).then(|| current_index - 1),
+        };
+
+        if let Some(index) = target_index {
+            self.force_load(index);
         }
     }
 
-    fn load_and_play_song(&mut self) {
-        self.stop_current_song(); // Pare a música atual
-        self.load_song(); // Carrega a nova música
-        self.play(); // Reproduz a nova música
+    fn next_song(&mut self) {
+        let Some(next_index) = self.advance_index() else {
+            self.stop(); // Sem mais músicas para tocar.
+            return;
+        };
+        self.force_load(next_index);
     }
 
-    fn play(&mut self) {
-        if let Some(sink) = &self.sink {
-            sink.lock().unwrap().play();
-            sink.lock().unwrap().set_volume(self.volume); // Ajusta o volume na reprodução
-            self.start_time = Some(std::time::Instant::now());
+    /// Chamado quando o `AudioController` reporta que a faixa atual acabou.
+    /// Se a próxima já estava enfileirada (preload gapless), apenas
+    /// sincroniza o estado da UI; caso contrário carrega do zero.
+    fn handle_track_finished(&mut self) {
+        if self.advanced_for_current {
+            return;
+        }
+        self.advanced_for_current = true;
+
+        let Some(next_index) = self.advance_index() else {
+            self.controller.send(Command::Stop);
+            self.is_playing = false;
+            return;
+        };
+
+        if self.preloaded_index == Some(next_index) {
+            self.selected_song = Some(next_index);
+            self.preloaded_index = None;
+            self.current_time = 0.0;
+            self.total_time = 0.0; // Evita disparar handle_track_finished antes da metadata chegar.
+            self.advanced_for_current = false;
             self.is_playing = true;
+            if let Some(path) = self.songs.get(next_index).cloned() {
+                self.request_metadata(next_index, path);
+            }
+            self.preload_next_song();
+        } else {
+            self.force_load(next_index);
         }
     }
 
-    fn stop(&mut self) {
-        self.stop_current_song();
+    /// Decide o próximo índice segundo o `playback_mode` atual e empurra o
+    /// índice atual para `history`. Não toca nada sozinho.
+    fn advance_index(&mut self) -> Option<usize> {
+        let current_index = self.selected_song?;
+        self.history.push(current_index);
+
+        match self.playback_mode {
+            PlaybackMode::RepeatOne => Some(current_index),
+            PlaybackMode::RepeatAll => {
+                if current_index + 1 < self.songs.len() {
+                    Some(current_index + 1)
+                } else {
+                    Some(0)
+                }
+            }
+            PlaybackMode::Shuffle => self.next_shuffle_index(),
+            PlaybackMode::Normal => {
+                (current_index + 1 < self.songs.len()).then(|| current_index + 1)
+            }
+        }
     }
 
-    fn stop_current_song(&mut self) {
-        if let Some(sink) = &self.sink {
-            sink.lock().unwrap().stop();
+    /// Consome o próximo índice da pilha de embaralhamento, reembaralhando
+    /// (excluindo a música que acabou de tocar) quando ela se esgota.
+    fn next_shuffle_index(&mut self) -> Option<usize> {
+        if self.songs.len() <= 1 {
+            return (!self.songs.is_empty()).then_some(0);
         }
-        self.is_playing = false;
-        self.current_time = 0.0;
+
+        if self.shuffle_order.is_empty() {
+            self.rebuild_shuffle_order();
+        }
+
+        self.shuffle_order.pop()
     }
 
-    fn load_song(&mut self) {
-        if let Some(index) = self.selected_song {
-            if let Some(path) = self.songs.get(index) {
-                if let Some(sink) = &self.sink {
-                    if let Ok(file) = File::open(path) {
-                        // Carregando os metadados ID3
-                        if let Ok(tag) = Tag::read_from_path(path) {
-                            self.song_title = tag.title().unwrap_or("Unknown Title").to_string();
-                            self.artist_name = tag.artist().unwrap_or("Unknown Artist").to_string();
-                        } else {
-                            self.song_title =
-                                path.file_name().unwrap().to_string_lossy().to_string();
-                            self.artist_name = "Unknown Artist".to_owned();
-                        }
+    /// Recria a ordem embaralhada com todos os índices, exceto a música atual.
+    fn rebuild_shuffle_order(&mut self) {
+        let mut order: Vec<usize> = (0..self.songs.len())
+            .filter(|&i| Some(i) != self.selected_song)
+            .collect();
+        order.shuffle(&mut thread_rng());
+        self.shuffle_order = order;
+    }
 
-                        match mp3_duration::from_file(&File::open(path).unwrap()) {
-                            Ok(duration) => {
-                                self.total_time = duration.as_secs_f32();
-                            }
-                            Err(e) => {
-                                eprintln!("Erro ao calcular a duração do MP3: {:?}", e);
-                                self.total_time = 0.0;
-                            }
-                        }
+    fn set_playback_mode(&mut self, mode: PlaybackMode) {
+        self.playback_mode = mode;
+        self.shuffle_order.clear();
+        if mode == PlaybackMode::Shuffle {
+            self.rebuild_shuffle_order();
+        }
+        self.preloaded_index = None;
+        self.preload_next_song();
+    }
 
-                        let source =
-                            Decoder::new(BufReader::new(file)).expect("Failed to decode audio");
+    /// Dispara o cálculo de título/artista/duração em uma thread separada
+    /// para não travar a UI em arquivos grandes ou montados em rede; o
+    /// resultado chega depois via `metadata_rx` e é aplicado em `poll_metadata`.
+    fn request_metadata(&self, index: usize, path: PathBuf) {
+        let tx = self.metadata_tx.clone();
+        std::thread::spawn(move || {
+            let metadata = decode::probe_metadata(&path);
+            let _ = tx.send((index, metadata));
+        });
+    }
 
-                        self.total_time = source
-                            .total_duration()
-                            .map(|d| d.as_secs_f32())
-                            .unwrap_or(0.0); // Obtém a duração real da música
+    /// Consome resultados de metadados já prontos, descartando os que
+    /// chegaram atrasados para uma música que não está mais selecionada.
+    fn poll_metadata(&mut self) {
+        while let Ok((index, metadata)) = self.metadata_rx.try_recv() {
+            if self.selected_song != Some(index) {
+                continue;
+            }
 
-                        sink.lock().unwrap().append(source);
-                        self.current_time = 0.0;
-                        self.song_finished.store(false, Ordering::SeqCst); // Reseta o estado de término da música
-                    } else {
-                        self.song_title = "Failed to load song".to_owned();
-                        self.artist_name = "Unknown Artist".to_owned();
+            let fallback_title = self
+                .songs
+                .get(index)
+                .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            match metadata {
+                Some(metadata) => {
+                    self.song_title = metadata.title.unwrap_or(fallback_title);
+                    self.artist_name =
+                        metadata.artist.unwrap_or_else(|| "Unknown Artist".to_owned());
+                    self.total_time = metadata.duration_secs;
+                }
+                None => {
+                    self.song_title = fallback_title;
+                    self.artist_name = "Unknown Artist".to_owned();
+                    self.total_time = 0.0;
+                }
+            }
+        }
+    }
+
+    /// Drena os eventos do `AudioController`, atualizando a posição
+    /// reportada pelo sink e disparando o avanço de faixa quando ela acaba.
+    fn poll_controller_status(&mut self) {
+        while let Some(status) = self.controller.try_recv_status() {
+            match status {
+                Status::NowPlaying => self.is_playing = true,
+                Status::Paused => self.is_playing = false,
+                Status::Stopped => {
+                    self.is_playing = false;
+                    self.current_time = 0.0;
+                }
+                Status::Position(position) => {
+                    self.current_time = position;
+                    if self.total_time > 0.0
+                        && position + TRACK_END_EPSILON_SECS >= self.total_time
+                    {
+                        self.handle_track_finished();
                     }
                 }
+                // O controller já detectou que o sink avançou sozinho para a
+                // faixa enfileirada; não há necessidade do fallback por tempo.
+                Status::TrackChanged => self.handle_track_finished(),
+                Status::TrackFinished => self.handle_track_finished(),
             }
         }
     }
 
-    fn check_song_finished(&mut self) {
-        if self.song_finished.load(Ordering::SeqCst) {
-            self.song_finished.store(false, Ordering::SeqCst); // Reseta o indicador
-            self.next_song(); // Avança para a próxima música
+    /// Enfileira a próxima música no `AudioController` com antecedência para
+    /// que ela comece a tocar sem gap assim que a atual terminar. Deve ser
+    /// chamado de novo sempre que `selected_song`, o diretório ou o
+    /// `playback_mode` mudarem.
+    fn preload_next_song(&mut self) {
+        let Some(current_index) = self.selected_song else {
+            self.preloaded_index = None;
+            return;
+        };
+
+        let next_index = match self.playback_mode {
+            PlaybackMode::RepeatOne => Some(current_index),
+            PlaybackMode::RepeatAll => {
+                if current_index + 1 < self.songs.len() {
+                    Some(current_index + 1)
+                } else {
+                    Some(0)
+                }
+            }
+            PlaybackMode::Shuffle => self.shuffle_order.last().copied(),
+            PlaybackMode::Normal => {
+                (current_index + 1 < self.songs.len()).then(|| current_index + 1)
+            }
+        };
+
+        let Some(next_index) = next_index else {
+            self.preloaded_index = None;
+            return;
+        };
+        if self.preloaded_index == Some(next_index) {
+            return; // Já enfileirada.
         }
+        let Some(next_path) = self.songs.get(next_index).cloned() else {
+            self.preloaded_index = None;
+            return;
+        };
+
+        self.controller.send(Command::QueueNext(next_path));
+        self.preloaded_index = Some(next_index);
+    }
+
+    /// Interrompe o que estiver tocando e carrega `index` do zero no
+    /// `AudioController`, pedindo os metadados em paralelo.
+    fn force_load(&mut self, index: usize) {
+        let Some(path) = self.songs.get(index).cloned() else {
+            return;
+        };
+
+        self.selected_song = Some(index);
+        self.preloaded_index = None;
+        self.advanced_for_current = false;
+        self.song_title = "Loading…".to_owned();
+        self.artist_name = String::new();
+        self.current_time = 0.0;
+        self.total_time = 0.0; // Evita disparar handle_track_finished antes da metadata chegar.
+        self.is_playing = true;
+
+        self.controller.send(Command::Load(path.clone()));
+        self.request_metadata(index, path);
+        self.preload_next_song();
+    }
+
+    fn play(&mut self) {
+        self.controller.send(Command::Play);
+    }
+
+    fn stop(&mut self) {
+        self.controller.send(Command::Stop);
+        self.is_playing = false;
+        self.current_time = 0.0;
+        self.preloaded_index = None;
     }
 
     fn update_directory(&mut self) {
         if let Some(directory) = FileDialog::new().pick_folder() {
             if let Some(dir_str) = directory.to_str() {
                 self.current_directory = dir_str.to_owned();
-                self.songs = Self::read_songs_from_directory(&self.current_directory); // Carrega as músicas do novo diretório
+                let directory = self.current_directory.clone();
+                self.spawn_directory_scan(&directory); // Carrega as músicas do novo diretório em segundo plano
                 self.selected_song = None; // Reseta a seleção
+                self.preloaded_index = None; // O preload antigo não corresponde ao novo diretório
+                self.history.clear();
+                self.shuffle_order.clear();
+                self.search_query.clear();
             }
         }
     }
 
-    fn update_progress(&mut self, value: f32) {
-        self.current_time = value;
-        // Aqui você pode implementar a lógica para buscar na stream de áudio
-    }
+    /// Salva o diretório atual como uma playlist nomeada, persistida em disco.
+    fn save_current_directory_as_playlist(&mut self) {
+        if self.songs.is_empty() {
+            return;
+        }
 
-    fn update_time(&mut self) {
-        if let Some(start_time) = self.start_time {
-            let elapsed = start_time.elapsed().as_secs_f32();
+        let name = PathBuf::from(&self.current_directory)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.current_directory.clone());
 
-            // Se o áudio é muito curto, normalize a atualização
-            let normalized_elapsed = if self.total_time <= 10.0 {
-                // Para áudios com 10 segundos ou menos, normaliza o tempo
-                elapsed * (self.total_time / 10.0)
-            } else {
-                elapsed
-            };
+        self.playlists.push(Playlist {
+            name,
+            songs: self.songs.clone(),
+        });
+        playlist::save(&self.playlists);
+    }
+
+    /// Troca a lista de músicas atual pela playlist salva em `index`.
+    fn load_playlist(&mut self, index: usize) {
+        let Some(playlist) = self.playlists.get(index).cloned() else {
+            return;
+        };
+
+        self.scan_generation += 1; // Descarta o que o scan do diretório ainda for entregar
+        self.songs = playlist.songs;
+        self.selected_song = None;
+        self.preloaded_index = None;
+        self.history.clear();
+        self.shuffle_order.clear();
+        self.search_query.clear();
+    }
 
-            self.current_time = (self.current_time + normalized_elapsed).min(self.total_time);
-            self.start_time = Some(std::time::Instant::now()); // Reinicia o temporizador
+    /// Índices de `self.songs` cujo nome de arquivo casa com `search_query`,
+    /// ordenados por relevância decrescente (lista completa se a busca estiver vazia).
+    fn filtered_song_indices(&self) -> Vec<usize> {
+        if self.search_query.is_empty() {
+            return (0..self.songs.len()).collect();
         }
+
+        let mut scored: Vec<(usize, i64)> = self
+            .songs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, path)| {
+                let file_name = path.file_name()?.to_string_lossy().into_owned();
+                self.matcher
+                    .fuzzy_match(&file_name, &self.search_query)
+                    .map(|score| (index, score))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.cmp(&a.1));
+        scored.into_iter().map(|(index, _)| index).collect()
     }
 
     fn adjust_volume(&mut self) {
-        if let Some(sink) = &self.sink {
-            sink.lock().unwrap().set_volume(self.volume);
-        }
+        self.controller.send(Command::SetVolume(self.volume));
     }
 
     fn pause(&mut self) {
-        if let Some(sink) = &self.sink {
-            sink.lock().unwrap().pause();
-            self.is_playing = false;
-            self.start_time = None;
-        }
+        self.controller.send(Command::Pause);
+        self.is_playing = false;
     }
 
     fn seek(&mut self, position: f32) {
-        if let Some(index) = self.selected_song {
-            if let Some(path) = self.songs.get(index) {
-                if let Some(sink) = &self.sink {
-                    sink.lock().unwrap().stop(); // Para a reprodução atual
-
-                    if let Ok(file) = File::open(path) {
-                        let source =
-                            Decoder::new(BufReader::new(file)).expect("Failed to decode audio");
-
-                        // Avança a posição no fluxo de áudio
-                        let skipped_source =
-                            source.skip_duration(std::time::Duration::from_secs_f32(position));
-
-                        self.current_time = position.min(self.total_time); // Atualiza o tempo atual
-
-                        // Adiciona a nova fonte ao sink e retoma a reprodução
-                        sink.lock().unwrap().append(skipped_source);
-                        sink.lock().unwrap().play();
-                    }
-                }
-            }
-        }
+        self.current_time = position.min(self.total_time);
+        self.controller.send(Command::Seek(position));
+        // O Seek do controller descarta a faixa enfileirada (o `stop()` que
+        // ele faz limpa o que ainda não tocou), então o preload precisa ser
+        // reenfileirado para a UI não achar que algo ainda está na fila.
+        self.preloaded_index = None;
+        self.preload_next_song();
     }
 }
 
 impl eframe::App for MediaPlayerApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.update_time();
-        self.check_song_finished();
+        self.poll_controller_status();
+        self.poll_metadata();
+        self.poll_directory_scan();
 
         egui::SidePanel::left("side_panel")
             .default_width(225.0) // Define a largura padrão do painel para 200 pixels
             .min_width(175.0) // Define a largura mínima do painel para 150 pixels
             .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Save Playlist").clicked() {
+                        self.save_current_directory_as_playlist();
+                    }
+                });
+                if !self.playlists.is_empty() {
+                    let mut selected_playlist = None;
+                    ui.horizontal_wrapped(|ui| {
+                        for (index, playlist) in self.playlists.iter().enumerate() {
+                            if ui.selectable_label(false, &playlist.name).clicked() {
+                                selected_playlist = Some(index);
+                            }
+                        }
+                    });
+                    if let Some(index) = selected_playlist {
+                        self.load_playlist(index);
+                    }
+                }
+                ui.add_space(5.0);
+
                 ui.heading("Music List");
-                ui.add_space(10.0);
+                ui.add_space(5.0);
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.search_query)
+                        .hint_text("Search…")
+                        .desired_width(f32::INFINITY),
+                );
+                ui.add_space(5.0);
 
                 if !self.songs.is_empty() {
                     let mut selected_index = None;
+                    let filtered_indices = self.filtered_song_indices();
 
                     ui.vertical(|ui| {
-                        for (index, song) in self.songs.iter().enumerate() {
+                        for index in filtered_indices {
+                            let song = &self.songs[index];
                             if ui
                                 .selectable_label(
                                     self.selected_song == Some(index),
@@ -312,8 +620,7 @@ impl eframe::App for MediaPlayerApp {
                     });
 
                     if let Some(index) = selected_index {
-                        self.selected_song = Some(index);
-                        self.load_and_play_song(); // Carrega a música selecionada
+                        self.force_load(index); // Carrega a música selecionada
                     }
                 } else {
                     ui.label("No songs available.");
@@ -395,7 +702,7 @@ impl eframe::App for MediaPlayerApp {
                         ui.horizontal(|ui| {
                             // let button_width = 78.0; // Divide a largura disponível igualmente entre os 4 botões
                             let button_width = ui.available_width() / 4.0 - 6.0; // Divide a largura disponível igualmente entre os 4 botões
-                            
+
                             if ui
                                 .add_sized([button_width, 30.0], egui::Button::new("Back"))
                                 .clicked()
@@ -418,8 +725,6 @@ impl eframe::App for MediaPlayerApp {
                                 .add_sized([button_width, 30.0], egui::Button::new("Next"))
                                 .clicked()
                             {
-                                self.update_progress(0.0);
-                                self.stop(); // Parar a música atual
                                 self.next_song(); // Avançar para a próxima música
                             }
 
@@ -447,6 +752,27 @@ impl eframe::App for MediaPlayerApp {
                         });
                     });
                     ui.end_row();
+
+                    ui.with_layout(self.layout.layout(), |ui| {
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            for mode in [
+                                PlaybackMode::Normal,
+                                PlaybackMode::RepeatOne,
+                                PlaybackMode::RepeatAll,
+                                PlaybackMode::Shuffle,
+                            ] {
+                                if ui
+                                    .selectable_label(self.playback_mode == mode, mode.label())
+                                    .clicked()
+                                {
+                                    self.set_playback_mode(mode);
+                                }
+                            }
+                        });
+                        ui.add_space(10.0);
+                    });
+                    ui.end_row();
                 });
         });
 
@@ -466,3 +792,126 @@ fn main() -> eframe::Result<()> {
         Box::new(|_cc| Ok(Box::<MediaPlayerApp>::default())),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `MediaPlayerApp::default()` spawns a real `AudioController` thread, mas
+    /// `run()` retorna de imediato quando não há saída de áudio disponível
+    /// (ver o `Err(_) => return` em `audio_controller::run`), então é seguro
+    /// usá-lo aqui mesmo num ambiente headless. Os testes sobrescrevem
+    /// `songs`/`selected_song` diretamente em vez de depender do scan
+    /// assíncrono do diretório inicial.
+    fn test_app(song_count: usize) -> MediaPlayerApp {
+        let mut app = MediaPlayerApp::default();
+        app.songs = (0..song_count)
+            .map(|i| PathBuf::from(format!("song_{i}.mp3")))
+            .collect();
+        app.selected_song = (song_count > 0).then_some(0);
+        app
+    }
+
+    #[test]
+    fn normal_mode_advances_sequentially() {
+        let mut app = test_app(3);
+        app.playback_mode = PlaybackMode::Normal;
+        assert_eq!(app.advance_index(), Some(1));
+    }
+
+    #[test]
+    fn normal_mode_stops_after_the_last_song() {
+        let mut app = test_app(3);
+        app.playback_mode = PlaybackMode::Normal;
+        app.selected_song = Some(2);
+        assert_eq!(app.advance_index(), None);
+    }
+
+    #[test]
+    fn repeat_one_replays_the_same_index() {
+        let mut app = test_app(3);
+        app.playback_mode = PlaybackMode::RepeatOne;
+        app.selected_song = Some(1);
+        assert_eq!(app.advance_index(), Some(1));
+    }
+
+    #[test]
+    fn repeat_all_wraps_back_to_the_start() {
+        let mut app = test_app(3);
+        app.playback_mode = PlaybackMode::RepeatAll;
+        app.selected_song = Some(2);
+        assert_eq!(app.advance_index(), Some(0));
+    }
+
+    #[test]
+    fn shuffle_visits_every_song_once_before_repeating() {
+        let mut app = test_app(4);
+        app.playback_mode = PlaybackMode::Shuffle;
+
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..3 {
+            let next = app
+                .advance_index()
+                .expect("shuffle must always have a next song while songs.len() > 1");
+            seen.insert(next);
+            app.selected_song = Some(next);
+        }
+        assert_eq!(seen.len(), 3);
+    }
+
+    #[test]
+    fn shuffle_with_a_single_song_loops_on_itself() {
+        let mut app = test_app(1);
+        app.playback_mode = PlaybackMode::Shuffle;
+        assert_eq!(app.advance_index(), Some(0));
+        assert_eq!(app.advance_index(), Some(0));
+    }
+
+    #[test]
+    fn shuffle_with_no_songs_has_no_next() {
+        let mut app = test_app(0);
+        app.playback_mode = PlaybackMode::Shuffle;
+        assert_eq!(app.advance_index(), None);
+    }
+
+    #[test]
+    fn filtered_song_indices_returns_everything_in_order_when_query_is_empty() {
+        let app = test_app(3);
+        assert_eq!(app.filtered_song_indices(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn filtered_song_indices_sorts_by_descending_score_and_maps_to_original_indices() {
+        let mut app = test_app(0);
+        app.songs = vec![
+            PathBuf::from("unrelated_track.mp3"),
+            PathBuf::from("bohemian_rhapsody.mp3"),
+            PathBuf::from("bohemian.mp3"),
+        ];
+        app.search_query = "bohemian".to_owned();
+
+        let indices = app.filtered_song_indices();
+
+        // "unrelated_track.mp3" has no 'b' at all, so it can't fuzzy-match.
+        assert!(!indices.contains(&0));
+        assert!(indices.contains(&1));
+        assert!(indices.contains(&2));
+
+        let scores: Vec<i64> = indices
+            .iter()
+            .map(|&index| {
+                let file_name = app.songs[index]
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                app.matcher
+                    .fuzzy_match(&file_name, &app.search_query)
+                    .expect("index was returned by filtered_song_indices, so it must match")
+            })
+            .collect();
+        let mut sorted_desc = scores.clone();
+        sorted_desc.sort_by(|a, b| b.cmp(a));
+        assert_eq!(scores, sorted_desc);
+    }
+}
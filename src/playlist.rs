@@ -0,0 +1,56 @@
+//! Saved playlists, persisted to disk so they survive restarts and can be
+//! reselected from a picker instead of re-browsing the filesystem every time.
+//!
+//! Persistence only compiles in with the `serde` feature, the same gate
+//! `LayoutSettings` uses for its own (de)serialization in `main.rs`; without
+//! it `load`/`save` are harmless no-ops so the playlist picker still works
+//! for the current session, it just doesn't survive a restart.
+
+use std::path::PathBuf;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Playlist {
+    pub name: String,
+    pub songs: Vec<PathBuf>,
+}
+
+#[cfg(feature = "serde")]
+fn storage_path() -> Option<PathBuf> {
+    let mut dir = dirs::config_dir()?;
+    dir.push("arca_music");
+    std::fs::create_dir_all(&dir).ok()?;
+    dir.push("playlists.json");
+    Some(dir)
+}
+
+/// Retorna uma lista vazia se o arquivo não existir, estiver corrompido, ou
+/// se a feature `serde` não estiver habilitada nesta build.
+#[cfg(feature = "serde")]
+pub fn load() -> Vec<Playlist> {
+    let Some(path) = storage_path() else {
+        return Vec::new();
+    };
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_default()
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn load() -> Vec<Playlist> {
+    Vec::new()
+}
+
+#[cfg(feature = "serde")]
+pub fn save(playlists: &[Playlist]) {
+    let Some(path) = storage_path() else {
+        return;
+    };
+    if let Ok(json) = serde_json::to_string_pretty(playlists) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+pub fn save(_playlists: &[Playlist]) {}